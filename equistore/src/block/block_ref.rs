@@ -5,7 +5,7 @@ use crate::c_api::{eqs_block_t, eqs_array_t, eqs_labels_t};
 use crate::c_api::EQS_INVALID_PARAMETER_ERROR;
 
 use crate::errors::check_status;
-use crate::{ArrayRef, Labels, Error};
+use crate::{ArrayRef, Labels, LabelValue, Error};
 
 use super::TensorBlock;
 
@@ -234,6 +234,201 @@ impl<'a> TensorBlockRef<'a> {
             block: self.as_ptr(),
         }
     }
+
+    /// Get a view inside the values of this block corresponding to the given
+    /// `sample` and `property`.
+    ///
+    /// If the block has components, the view contains all the components for
+    /// this sample/property pair. Otherwise, the view is a zero-dimensional
+    /// array containing a single value.
+    ///
+    /// This returns an error if either the `sample` or the `property` are not
+    /// part of this block, or if the values are not stored in a
+    /// `ndarray::ArrayD`.
+    #[inline]
+    pub fn datum_at(
+        &self,
+        sample: &[LabelValue],
+        property: &[LabelValue],
+    ) -> Result<ndarray::ArrayViewD<'a, f64>, Error> {
+        let values = self.values();
+
+        let sample_i = find_position(&values.samples, sample, "sample")?;
+        let property_i = find_position(&values.properties, property, "property")?;
+
+        let view = values.data.try_to_array()?.view().index_axis_move(ndarray::Axis(0), sample_i);
+        let last_axis = view.ndim() - 1;
+        return Ok(view.index_axis_move(ndarray::Axis(last_axis), property_i));
+    }
+
+    /// Create a new [`TensorBlock`] containing a copy of the gradient with
+    /// respect to `parameter` in this block.
+    ///
+    /// The gradient samples, components and properties become the samples,
+    /// components and properties of the new block, meaning that the extra
+    /// gradient components (e.g. cartesian directions) are treated as regular
+    /// components.
+    ///
+    /// This returns an error if there is no gradient with respect to
+    /// `parameter`, or if the gradient data is not stored in a
+    /// `ndarray::ArrayD`.
+    #[inline]
+    pub fn gradient_as_block(&self, parameter: &str) -> Result<TensorBlock, Error> {
+        let gradient = self.gradient(parameter).ok_or_else(|| Error {
+            code: None,
+            message: format!("missing gradient with respect to '{}' in this block", parameter),
+        })?;
+
+        return TensorBlock::new(
+            gradient.data.try_to_array()?.clone(),
+            gradient.samples,
+            &gradient.components,
+            gradient.properties,
+        );
+    }
+
+    /// Add the gradient with respect to `parameter` in this block to the
+    /// gradient with respect to the same parameter in `target`.
+    ///
+    /// Both gradients must exist, have the same samples, components and
+    /// properties labels, and be stored in `ndarray::ArrayD`; otherwise this
+    /// function returns an error.
+    #[inline]
+    pub fn accumulate_gradient_into(&self, parameter: &str, target: &mut TensorBlock) -> Result<(), Error> {
+        let gradient = self.gradient(parameter).ok_or_else(|| Error {
+            code: None,
+            message: format!("missing gradient with respect to '{}' in this block", parameter),
+        })?;
+
+        let mut target = target.as_ref_mut();
+        let mut target_gradient = target.gradient_mut(parameter).ok_or_else(|| Error {
+            code: None,
+            message: format!("missing gradient with respect to '{}' in the target block", parameter),
+        })?;
+
+        if gradient.samples != target_gradient.samples
+            || gradient.components != target_gradient.components
+            || gradient.properties != target_gradient.properties {
+            return Err(Error {
+                code: None,
+                message: format!(
+                    "can not accumulate gradients with respect to '{}': the \
+                    target block gradient has different metadata",
+                    parameter
+                ),
+            });
+        }
+
+        let gradient = gradient.data.try_to_array()?;
+        *target_gradient.data.try_as_array_mut()? += gradient;
+
+        return Ok(());
+    }
+
+    /// Write the values of this block inside the `target` array, starting at
+    /// `sample_offset` along the first (samples) axis and at `property_offset`
+    /// along the last (properties) axis.
+    ///
+    /// The `target` array must have the same number of dimensions as the
+    /// values, and the same size for all the components dimensions. This
+    /// function returns an error if the values would be written outside of
+    /// `target`, or if the values of this block are not stored in a
+    /// `ndarray::ArrayD`.
+    #[inline]
+    pub fn scatter_into(
+        &self,
+        target: &mut ndarray::ArrayViewMutD<f64>,
+        sample_offset: usize,
+        property_offset: usize,
+    ) -> Result<(), Error> {
+        let values = self.values();
+        let array = values.data.try_to_array()?;
+
+        let shape = array.shape();
+        let target_shape = target.shape();
+        if shape.len() != target_shape.len() {
+            return Err(Error {
+                code: None,
+                message: format!(
+                    "invalid target array in scatter_into: expected {} dimensions, got {}",
+                    shape.len(), target_shape.len()
+                ),
+            });
+        }
+
+        let n_dims = shape.len();
+        if shape[1..n_dims - 1] != target_shape[1..n_dims - 1] {
+            return Err(Error {
+                code: None,
+                message: format!(
+                    "invalid target array in scatter_into: components shape \
+                    should be {:?}, got {:?}",
+                    &shape[1..n_dims - 1], &target_shape[1..n_dims - 1]
+                ),
+            });
+        }
+
+        let samples_end = match sample_offset.checked_add(shape[0]) {
+            Some(end) if end <= target_shape[0] => end,
+            _ => {
+                return Err(Error {
+                    code: None,
+                    message: format!(
+                        "out of bounds write in scatter_into: {} samples starting \
+                        at {} do not fit in a target with {} samples",
+                        shape[0], sample_offset, target_shape[0]
+                    ),
+                });
+            }
+        };
+
+        let properties_end = match property_offset.checked_add(shape[n_dims - 1]) {
+            Some(end) if end <= target_shape[n_dims - 1] => end,
+            _ => {
+                return Err(Error {
+                    code: None,
+                    message: format!(
+                        "out of bounds write in scatter_into: {} properties \
+                        starting at {} do not fit in a target with {} properties",
+                        shape[n_dims - 1], property_offset, target_shape[n_dims - 1]
+                    ),
+                });
+            }
+        };
+
+        let mut output = target.slice_each_axis_mut(|axis| {
+            let axis = axis.axis.index();
+            if axis == 0 {
+                ndarray::Slice::from(sample_offset..samples_end)
+            } else if axis == n_dims - 1 {
+                ndarray::Slice::from(property_offset..properties_end)
+            } else {
+                ndarray::Slice::from(..)
+            }
+        });
+        output.assign(array);
+
+        return Ok(());
+    }
+}
+
+/// Get the position of `entry` in `labels`, returning an error mentioning
+/// `kind` if the entry is not part of the labels.
+pub(super) fn find_position(labels: &Labels, entry: &[LabelValue], kind: &str) -> Result<usize, Error> {
+    if entry.len() != labels.size() {
+        return Err(Error {
+            code: None,
+            message: format!(
+                "invalid {} size: expected {} values, got {}",
+                kind, labels.size(), entry.len()
+            ),
+        });
+    }
+
+    return labels.position(entry).ok_or_else(|| Error {
+        code: None,
+        message: format!("{} {:?} is not part of this block", kind, entry),
+    });
 }
 
 /// Iterator over parameter/[`BasicBlock`] pairs for all gradients in a
@@ -294,4 +489,194 @@ mod tests {
         assert_eq!(std::mem::size_of::<TensorBlockRef>(), std::mem::size_of::<*const eqs_block_t>());
         assert_eq!(std::mem::align_of::<TensorBlockRef>(), std::mem::align_of::<*const eqs_block_t>());
     }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn datum_at() {
+        let block = TensorBlock::new(
+            ndarray::ArrayD::from_shape_fn(vec![2, 3, 2], |i| (100 * i[0] + 10 * i[1] + i[2]) as f64),
+            Labels::new(["samples"], &[[0], [3]]),
+            &[Labels::new(["components"], &[[-1], [0], [1]])],
+            Labels::new(["properties"], &[[4], [5]]),
+        ).unwrap();
+        let block = block.as_ref();
+
+        let datum = block.datum_at(&[LabelValue::new(3)], &[LabelValue::new(4)]).unwrap();
+        assert_eq!(datum, ndarray::arr1(&[100.0, 110.0, 120.0]).into_dyn());
+
+        let error = block.datum_at(&[LabelValue::new(1)], &[LabelValue::new(4)]).unwrap_err();
+        assert_eq!(error.message, "sample [1] is not part of this block");
+
+        let error = block.datum_at(&[LabelValue::new(0)], &[]).unwrap_err();
+        assert_eq!(error.message, "invalid property size: expected 1 values, got 0");
+    }
+
+    #[test]
+    fn gradient_as_block() {
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 3], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+
+        block.add_gradient(
+            "positions",
+            ndarray::ArrayD::from_elem(vec![4, 3, 3], 2.0),
+            Labels::new(["sample", "atom"], &[[0, 0], [0, 1], [1, 0], [1, 2]]),
+            &[Labels::new(["xyz"], &[[0], [1], [2]])],
+        ).unwrap();
+
+        let gradient = block.as_ref().gradient_as_block("positions").unwrap();
+        let values = gradient.as_ref().values();
+        assert_eq!(values.samples, Labels::new(["sample", "atom"], &[[0, 0], [0, 1], [1, 0], [1, 2]]));
+        assert_eq!(values.components, [Labels::new(["xyz"], &[[0], [1], [2]])]);
+        assert_eq!(values.properties, Labels::new(["properties"], &[[0], [1], [2]]));
+        assert_eq!(values.data.as_array(), ndarray::ArrayD::from_elem(vec![4, 3, 3], 2.0));
+        assert!(gradient.as_ref().gradient_list().is_empty());
+
+        let error = block.as_ref().gradient_as_block("cell").unwrap_err();
+        assert_eq!(error.message, "missing gradient with respect to 'cell' in this block");
+    }
+
+    #[test]
+    fn accumulate_gradient_into() {
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 3], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+        block.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_elem(vec![2, 3], 2.0),
+            Labels::new(["sample", "parameter"], &[[0, 1], [1, 1]]),
+            &[],
+        ).unwrap();
+
+        let mut target = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 3], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+        target.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_elem(vec![2, 3], 1.5),
+            Labels::new(["sample", "parameter"], &[[0, 1], [1, 1]]),
+            &[],
+        ).unwrap();
+
+        block.as_ref().accumulate_gradient_into("parameter", &mut target).unwrap();
+        block.as_ref().accumulate_gradient_into("parameter", &mut target).unwrap();
+
+        let gradient = target.as_ref().gradient("parameter").unwrap();
+        assert_eq!(gradient.data.as_array(), ndarray::ArrayD::from_elem(vec![2, 3], 5.5));
+
+        let error = block.as_ref().accumulate_gradient_into("other", &mut target).unwrap_err();
+        assert_eq!(error.message, "missing gradient with respect to 'other' in this block");
+
+        let mut target = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 3], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+        target.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_elem(vec![1, 3], 1.0),
+            Labels::new(["sample", "parameter"], &[[0, 1]]),
+            &[],
+        ).unwrap();
+
+        let error = block.as_ref().accumulate_gradient_into("parameter", &mut target).unwrap_err();
+        assert_eq!(
+            error.message,
+            "can not accumulate gradients with respect to 'parameter': the target block gradient has different metadata"
+        );
+
+        let mut empty = TensorBlock::new(
+            crate::EmptyArray::new(vec![2, 3]),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+        empty.add_gradient(
+            "parameter",
+            crate::EmptyArray::new(vec![2, 3]),
+            Labels::new(["sample", "parameter"], &[[0, 1], [1, 1]]),
+            &[],
+        ).unwrap();
+
+        let error = block.as_ref().accumulate_gradient_into("parameter", &mut empty).unwrap_err();
+        assert_eq!(error.message, "this array is not a ndarray::ArrayD");
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn scatter_into() {
+        let block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 3], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+
+        let mut output = ndarray::ArrayD::from_elem(vec![4, 5], 0.0);
+        block.as_ref().scatter_into(&mut output.view_mut(), 1, 2).unwrap();
+
+        assert_eq!(output.sum(), 6.0);
+        assert_eq!(output[[0, 2]], 0.0);
+        assert_eq!(output[[1, 1]], 0.0);
+        assert_eq!(output[[1, 2]], 1.0);
+        assert_eq!(output[[2, 4]], 1.0);
+        assert_eq!(output[[3, 4]], 0.0);
+
+        let error = block.as_ref().scatter_into(&mut output.view_mut(), 3, 0).unwrap_err();
+        assert_eq!(
+            error.message,
+            "out of bounds write in scatter_into: 2 samples starting at 3 do not fit in a target with 4 samples"
+        );
+
+        let error = block.as_ref().scatter_into(&mut output.view_mut(), usize::MAX, 0).unwrap_err();
+        assert_eq!(
+            error.message,
+            format!("out of bounds write in scatter_into: 2 samples starting at {} do not fit in a target with 4 samples", usize::MAX)
+        );
+
+        let error = block.as_ref().scatter_into(&mut output.view_mut(), 0, 3).unwrap_err();
+        assert_eq!(
+            error.message,
+            "out of bounds write in scatter_into: 3 properties starting at 3 do not fit in a target with 5 properties"
+        );
+
+        let error = block.as_ref().scatter_into(&mut output.view_mut(), 0, usize::MAX - 1).unwrap_err();
+        assert_eq!(
+            error.message,
+            format!("out of bounds write in scatter_into: 3 properties starting at {} do not fit in a target with 5 properties", usize::MAX - 1)
+        );
+
+        let mut output = ndarray::ArrayD::from_elem(vec![4, 2, 5], 0.0);
+        let error = block.as_ref().scatter_into(&mut output.view_mut(), 0, 0).unwrap_err();
+        assert_eq!(error.message, "invalid target array in scatter_into: expected 2 dimensions, got 3");
+
+        let block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 3, 3], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[Labels::new(["components"], &[[0], [1], [2]])],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+        let error = block.as_ref().scatter_into(&mut output.view_mut(), 0, 0).unwrap_err();
+        assert_eq!(error.message, "invalid target array in scatter_into: components shape should be [3], got [2]");
+
+        let block = TensorBlock::new(
+            crate::EmptyArray::new(vec![2, 3]),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+        let mut output = ndarray::ArrayD::from_elem(vec![4, 5], 0.0);
+        let error = block.as_ref().scatter_into(&mut output.view_mut(), 0, 0).unwrap_err();
+        assert_eq!(error.message, "this array is not a ndarray::ArrayD");
+    }
 }
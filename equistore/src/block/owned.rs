@@ -1,8 +1,9 @@
 use crate::c_api::eqs_block_t;
 use crate::errors::check_status;
-use crate::{Array, Labels, Error, TensorBlockRef};
+use crate::{Array, Labels, LabelValue, Error, TensorBlockRef};
 
 use super::TensorBlockRefMut;
+use super::block_ref::find_position;
 
 /// A single block, containing both values & optionally gradients of these
 /// values w.r.t. any relevant quantity.
@@ -124,6 +125,49 @@ impl TensorBlock {
 
         return Ok(());
     }
+
+    /// Compute the outer product of the values of sample `a_sample` in block
+    /// `a` with the values of sample `b_sample` in block `b`.
+    ///
+    /// Without components, the output is an array of shape `[n_properties_a,
+    /// n_properties_b]`. Otherwise the shape of the output is the shape of the
+    /// row in `a` (`[components_a..., n_properties_a]`) followed by the shape
+    /// of the row in `b`.
+    ///
+    /// This returns an error if the samples are not part of the corresponding
+    /// blocks, or if the values are not stored in `ndarray::ArrayD`.
+    #[inline]
+    pub fn outer_product_rows(
+        a: &TensorBlock,
+        a_sample: &[LabelValue],
+        b: &TensorBlock,
+        b_sample: &[LabelValue],
+    ) -> Result<ndarray::ArrayD<f64>, Error> {
+        let a_values = a.as_ref().values();
+        let b_values = b.as_ref().values();
+
+        let a_sample = find_position(&a_values.samples, a_sample, "sample").map_err(|error| Error {
+            code: None,
+            message: format!("invalid sample for block `a`: {}", error.message),
+        })?;
+        let b_sample = find_position(&b_values.samples, b_sample, "sample").map_err(|error| Error {
+            code: None,
+            message: format!("invalid sample for block `b`: {}", error.message),
+        })?;
+
+        let a_row = a_values.data.try_to_array()?.index_axis(ndarray::Axis(0), a_sample);
+        let b_row = b_values.data.try_to_array()?.index_axis(ndarray::Axis(0), b_sample);
+
+        let mut shape = a_row.shape().to_vec();
+        shape.extend_from_slice(b_row.shape());
+
+        let product = a_row.iter()
+            .flat_map(|&a| b_row.iter().map(move |&b| a * b))
+            .collect::<Vec<_>>();
+
+        let product = ndarray::ArrayD::from_shape_vec(shape, product).expect("wrong shape in outer product");
+        return Ok(product);
+    }
 }
 
 
@@ -139,4 +183,53 @@ mod tests {
         assert_eq!(std::mem::size_of::<TensorBlock>(), std::mem::size_of::<*const eqs_block_t>());
         assert_eq!(std::mem::align_of::<TensorBlock>(), std::mem::align_of::<*const eqs_block_t>());
     }
+
+    #[test]
+    fn outer_product_rows() {
+        let a = TensorBlock::new(
+            ndarray::arr2(&[[1.0, 2.0], [3.0, 4.0]]).into_dyn(),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1]]),
+        ).unwrap();
+
+        let b = TensorBlock::new(
+            ndarray::arr3(&[[[1.0, -1.0, 0.5]], [[2.0, 0.0, 3.0]]]).into_dyn(),
+            Labels::new(["samples"], &[[3], [5]]),
+            &[Labels::new(["components"], &[[0]])],
+            Labels::new(["properties"], &[[0], [1], [2]]),
+        ).unwrap();
+
+        let product = TensorBlock::outer_product_rows(
+            &a, &[LabelValue::new(1)],
+            &b, &[LabelValue::new(3)],
+        ).unwrap();
+
+        let expected = ndarray::arr3(&[[[3.0, -3.0, 1.5]], [[4.0, -4.0, 2.0]]]).into_dyn();
+        assert_eq!(product, expected);
+
+        let error = TensorBlock::outer_product_rows(
+            &a, &[LabelValue::new(1)],
+            &b, &[LabelValue::new(4)],
+        ).unwrap_err();
+        assert_eq!(error.message, "invalid sample for block `b`: sample [4] is not part of this block");
+
+        let error = TensorBlock::outer_product_rows(
+            &a, &[LabelValue::new(3)],
+            &b, &[LabelValue::new(3)],
+        ).unwrap_err();
+        assert_eq!(error.message, "invalid sample for block `a`: sample [3] is not part of this block");
+
+        let empty = TensorBlock::new(
+            crate::EmptyArray::new(vec![2, 2]),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1]]),
+        ).unwrap();
+        let error = TensorBlock::outer_product_rows(
+            &a, &[LabelValue::new(1)],
+            &empty, &[LabelValue::new(0)],
+        ).unwrap_err();
+        assert_eq!(error.message, "this array is not a ndarray::ArrayD");
+    }
 }
@@ -89,6 +89,16 @@ impl<'a> ArrayRef<'a> {
         self.to_any().downcast_ref().expect("this is not a ndarray::ArrayD")
     }
 
+    /// Try to transform this `ArrayRef` into a reference to an
+    /// `ndarray::ArrayD`, keeping the lifetime of the `ArrayRef`.
+    ///
+    /// Contrary to [`ArrayRef::to_array`], this returns an error instead of
+    /// panicking if the data is not a `ndarray::ArrayD`.
+    pub(crate) fn try_to_array(self) -> Result<&'a ndarray::ArrayD<f64>, Error> {
+        check_rust_ndarray(&self.array)?;
+        return Ok(self.to_array());
+    }
+
     /// Get the raw underlying `eqs_array_t`
     pub fn as_raw(&self) -> &eqs_array_t {
         &self.array
@@ -236,6 +246,16 @@ impl<'a> ArrayRefMut<'a> {
         self.to_any_mut().downcast_mut().expect("this is not a ndarray::ArrayD")
     }
 
+    /// Try to get the data in this `ArrayRefMut` as a mutable reference to an
+    /// `ndarray::ArrayD`.
+    ///
+    /// Contrary to [`ArrayRefMut::as_array_mut`], this returns an error
+    /// instead of panicking if the data is not a `ndarray::ArrayD`.
+    pub(crate) fn try_as_array_mut(&mut self) -> Result<&mut ndarray::ArrayD<f64>, Error> {
+        check_rust_ndarray(&self.array)?;
+        return Ok(self.as_array_mut());
+    }
+
     /// Get the raw underlying `eqs_array_t`
     pub fn as_raw(&self) -> &eqs_array_t {
         &self.array
@@ -404,6 +424,35 @@ impl eqs_array_t {
     }
 }
 
+/// Check that the given array was created from Rust and contains a
+/// `ndarray::ArrayD<f64>`, returning an error otherwise.
+fn check_rust_ndarray(array: &eqs_array_t) -> Result<(), Error> {
+    let origin = array.origin()?;
+    if origin != *super::array::RUST_DATA_ORIGIN {
+        return Err(Error {
+            code: None,
+            message: format!(
+                "this array was not created as a rust Array (origin is '{}')",
+                get_data_origin(origin).unwrap_or_else(|_| "unknown".into())
+            ),
+        });
+    }
+
+    let array = array.ptr.cast::<Box<dyn Array>>();
+    let is_ndarray = unsafe {
+        (*array).as_any().is::<ndarray::ArrayD<f64>>()
+    };
+
+    if !is_ndarray {
+        return Err(Error {
+            code: None,
+            message: "this array is not a ndarray::ArrayD".into(),
+        });
+    }
+
+    return Ok(());
+}
+
 /// Check the status code returned by arbitrary functions inside an
 /// `eqs_array_t`
 fn check_status_external(status: eqs_status_t, function: &str) -> Result<(), Error> {
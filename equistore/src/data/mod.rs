@@ -31,6 +31,18 @@ mod tests {
         assert_eq!(array.as_raw().shape().unwrap(), [2, 12]);
     }
 
+    #[test]
+    fn try_to_array() {
+        let array = Box::new(ArrayD::from_elem(vec![4, 2], 1.0)) as Box<dyn Array>;
+        let array = unsafe { ArrayRef::from_raw(array.into()) };
+        assert_eq!(array.try_to_array().unwrap(), ArrayD::from_elem(vec![4, 2], 1.0));
+
+        let array = Box::new(EmptyArray::new(vec![4, 2])) as Box<dyn Array>;
+        let mut array = unsafe { ArrayRefMut::new(array.into()) };
+        let error = array.try_as_array_mut().unwrap_err();
+        assert_eq!(error.message, "this array is not a ndarray::ArrayD");
+    }
+
     #[test]
     fn create() {
         let array = Box::new(ArrayD::from_elem(vec![4, 2], 1.0)) as Box<dyn Array>;
@@ -1,12 +1,15 @@
 use std:: ffi::CStr;
 use std::ffi::CString;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::iter::FusedIterator;
+use std::sync::Arc;
 
+use once_cell::sync::OnceCell;
 use smallvec::SmallVec;
 
 use crate::c_api::eqs_labels_t;
 use crate::errors::check_status;
+use crate::Error;
 
 impl eqs_labels_t {
     /// Create an `eqs_labels_t` with all members set to null pointers/zero
@@ -130,13 +133,18 @@ impl LabelValue {
 /// should be a cheap operation.
 pub struct Labels {
     pub(crate) raw: eqs_labels_t,
+    /// lazily initialized map from entries to positions, see
+    /// `Labels::position_map_cached`. This cache is specific to this Rust
+    /// wrapper, and not shared with the labels inside equistore-core.
+    position_map: OnceCell<Arc<HashMap<Vec<LabelValue>, usize>>>,
 }
 
 // Labels can be sent to other thread safely since eqs_labels_t uses an
 // `Arc<equistore_core::Labels>`, so freeing them from another thread is fine
 unsafe impl Send for Labels {}
-// &Labels can be sent to other thread safely since there is no interior
-// mutability
+// &Labels can be sent to other thread safely: the only interior mutability is
+// the `position_map` cache, which uses `once_cell::sync::OnceCell` and is
+// `Sync` itself
 unsafe impl Sync for Labels {}
 
 impl std::fmt::Debug for Labels {
@@ -176,7 +184,13 @@ impl Clone for Labels {
             check_status(crate::c_api::eqs_labels_clone(self.raw, &mut clone)).expect("failed to clone Labels");
         }
 
-        return unsafe { Labels::from_raw(clone) };
+        let clone = unsafe { Labels::from_raw(clone) };
+        if let Some(position_map) = self.position_map.get() {
+            // the clone refers to the same labels, so it can share the cache
+            let _ = clone.position_map.set(Arc::clone(position_map));
+        }
+
+        return clone;
     }
 }
 
@@ -277,6 +291,58 @@ impl Labels {
         return result.try_into().ok();
     }
 
+    /// Count the number of entries with each of the values taken by the given
+    /// `dimension` in this set of labels.
+    ///
+    /// This returns an error if `dimension` is not one of the names of these
+    /// labels.
+    #[inline]
+    pub fn histogram(&self, dimension: &str) -> Result<HashMap<LabelValue, usize>, Error> {
+        let index = self.names().iter().position(|&name| name == dimension).ok_or_else(|| Error {
+            code: None,
+            message: format!(
+                "'{}' is not part of these labels, available names are [{}]",
+                dimension, self.names().join(", ")
+            ),
+        })?;
+
+        let mut histogram = HashMap::new();
+        for entry in self {
+            *histogram.entry(entry[index]).or_insert(0) += 1;
+        }
+
+        return Ok(histogram);
+    }
+
+    /// Build a map from each entry in this set of labels to its position.
+    ///
+    /// This can be used to speed up code calling [`Labels::position`] many
+    /// times. See also [`Labels::position_map_cached`].
+    #[inline]
+    pub fn build_position_map(&self) -> HashMap<Vec<LabelValue>, usize> {
+        return self.iter()
+            .enumerate()
+            .map(|(position, entry)| (entry.to_vec(), position))
+            .collect();
+    }
+
+    /// Get a map from each entry in this set of labels to its position.
+    ///
+    /// The map is built on the first call to this function (using
+    /// [`Labels::build_position_map`]), and cached for later calls.
+    ///
+    /// The cache belongs to this `Labels` instance (and its clones), not to
+    /// the underlying labels in equistore-core. In particular, functions such
+    /// as [`TensorBlockRef::values`](crate::TensorBlockRef::values) or
+    /// [`TensorBlockRef::gradient`](crate::TensorBlockRef::gradient) create new
+    /// `Labels` on every call, which will not share the cached map. Keep a
+    /// single `Labels` around to benefit from the cache.
+    #[inline]
+    pub fn position_map_cached(&self) -> Arc<HashMap<Vec<LabelValue>, usize>> {
+        let position_map = self.position_map.get_or_init(|| Arc::new(self.build_position_map()));
+        return Arc::clone(position_map);
+    }
+
     /// Iterate over the entries in this set of labels
     #[inline]
     pub fn iter(&self) -> LabelsIter<'_> {
@@ -336,6 +402,7 @@ impl Labels {
         assert!(!raw.internal_ptr_.is_null(), "expected eqs_labels_t.internal_ptr_ to not be NULL");
         Labels {
             raw: raw,
+            position_map: OnceCell::new(),
         }
     }
 }
@@ -347,6 +414,14 @@ impl std::cmp::PartialEq<Labels> for Labels {
     }
 }
 
+impl std::hash::Hash for Labels {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.names().hash(state);
+        self.values().hash(state);
+    }
+}
+
 impl std::ops::Index<usize> for Labels {
     type Output = [LabelValue];
 
@@ -471,6 +546,11 @@ pub struct LabelsBuilder {
     // cf `Labels` for the documentation of the fields
     names: Vec<String>,
     values: Vec<LabelValue>,
+    // position of the entries already added to this builder, lazily filled
+    // by `position_or_insert`
+    positions: HashMap<SmallVec<[LabelValue; 16]>, usize>,
+    // number of entries already indexed in `positions`
+    n_indexed: usize,
 }
 
 impl LabelsBuilder {
@@ -483,6 +563,8 @@ impl LabelsBuilder {
         LabelsBuilder {
             names: names.into_iter().map(|s| s.into()).collect(),
             values: Vec::new(),
+            positions: HashMap::new(),
+            n_indexed: 0,
         }
     }
 
@@ -516,6 +598,53 @@ impl LabelsBuilder {
         self.values.extend(&entry);
     }
 
+    /// Get the position of the given `entry` in this set of labels, adding it
+    /// at the end of the labels if it is not already present.
+    ///
+    /// The index used to find existing entries is built on the first call to
+    /// this function, and only updated with the entries added since the
+    /// previous call afterward.
+    ///
+    /// This function returns an error if the `entry` does not have the right
+    /// size.
+    #[inline]
+    pub fn position_or_insert(&mut self, entry: &[LabelValue]) -> Result<usize, Error> {
+        if entry.len() != self.size() {
+            return Err(Error {
+                code: None,
+                message: format!(
+                    "wrong size for label in position_or_insert: got {}, but expected {}",
+                    entry.len(), self.size()
+                ),
+            });
+        }
+
+        if self.size() == 0 {
+            // labels without names can contain at most one (empty) entry,
+            // which is not stored in `self.values`
+            self.positions.entry(SmallVec::new()).or_insert(0);
+            return Ok(0);
+        }
+
+        let count = self.values.len() / self.size();
+        for position in self.n_indexed..count {
+            let start = position * self.size();
+            let stop = (position + 1) * self.size();
+            self.positions.entry(self.values[start..stop].into()).or_insert(position);
+        }
+        self.n_indexed = count;
+
+        if let Some(&position) = self.positions.get(entry) {
+            return Ok(position);
+        }
+
+        self.values.extend_from_slice(entry);
+        self.positions.insert(entry.into(), count);
+        self.n_indexed += 1;
+
+        return Ok(count);
+    }
+
     /// Finish building the `Labels`
     #[inline]
     pub fn finish(self) -> Labels {
@@ -625,6 +754,102 @@ mod tests {
         builder.finish();
     }
 
+    #[test]
+    fn position_or_insert() {
+        let mut builder = LabelsBuilder::new(vec!["foo", "bar"]);
+        builder.add(&[2, 3]);
+        builder.add(&[1, 243]);
+
+        let entry = [LabelValue::new(1), LabelValue::new(243)];
+        assert_eq!(builder.position_or_insert(&entry).unwrap(), 1);
+
+        let entry = [LabelValue::new(-4), LabelValue::new(5)];
+        assert_eq!(builder.position_or_insert(&entry).unwrap(), 2);
+        assert_eq!(builder.position_or_insert(&entry).unwrap(), 2);
+
+        // entries added with `add` after the index was built are found
+        builder.add(&[7, 8]);
+        let entry = [LabelValue::new(7), LabelValue::new(8)];
+        assert_eq!(builder.position_or_insert(&entry).unwrap(), 3);
+
+        let error = builder.position_or_insert(&[LabelValue::new(3)]).unwrap_err();
+        assert_eq!(error.message, "wrong size for label in position_or_insert: got 1, but expected 2");
+
+        let labels = builder.finish();
+        assert_eq!(labels.count(), 4);
+        assert_eq!(labels[2], [-4, 5]);
+
+        let mut builder = LabelsBuilder::new(vec![]);
+        assert_eq!(builder.position_or_insert(&[]).unwrap(), 0);
+        assert_eq!(builder.position_or_insert(&[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn position_map() {
+        let labels = Labels::new(
+            ["foo", "bar"],
+            &[
+                [2, 3],
+                [1, 243],
+                [-4, -2413],
+            ]
+        );
+
+        let map = labels.build_position_map();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map[&vec![LabelValue::new(1), LabelValue::new(243)]], 1);
+        assert_eq!(map[&vec![LabelValue::new(-4), LabelValue::new(-2413)]], 2);
+
+        let cached = labels.position_map_cached();
+        assert_eq!(*cached, map);
+        assert!(Arc::ptr_eq(&cached, &labels.position_map_cached()));
+        assert!(Arc::ptr_eq(&cached, &labels.clone().position_map_cached()));
+    }
+
+    #[test]
+    fn position_map_cached_per_instance() {
+        let block = crate::TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![3, 1], 1.0),
+            Labels::new(["samples"], &[[0], [1], [2]]),
+            &[],
+            Labels::new(["properties"], &[[0]]),
+        ).unwrap();
+
+        // every call to `values()` creates new samples labels, with a separate
+        // cache
+        let first = block.as_ref().values().samples.position_map_cached();
+        let second = block.as_ref().values().samples.position_map_cached();
+        assert_eq!(first, second);
+        assert!(!Arc::ptr_eq(&first, &second));
+
+        // re-using the same labels re-uses the cache
+        let samples = block.as_ref().values().samples;
+        assert!(Arc::ptr_eq(&samples.position_map_cached(), &samples.position_map_cached()));
+    }
+
+    #[test]
+    fn histogram() {
+        let labels = Labels::new(
+            ["structure", "center"],
+            &[
+                [0, 1],
+                [0, 6],
+                [1, 1],
+                [1, 8],
+                [2, 8],
+            ]
+        );
+
+        let histogram = labels.histogram("structure").unwrap();
+        assert_eq!(histogram.len(), 3);
+        assert_eq!(histogram[&LabelValue::new(0)], 2);
+        assert_eq!(histogram[&LabelValue::new(1)], 2);
+        assert_eq!(histogram[&LabelValue::new(2)], 1);
+
+        let error = labels.histogram("species").unwrap_err();
+        assert_eq!(error.message, "'species' is not part of these labels, available names are [structure, center]");
+    }
+
     #[test]
     fn single_label() {
         let labels = Labels::single();
@@ -5,7 +5,7 @@ use crate::block::{TensorBlockRefMut};
 use crate::c_api::{eqs_tensormap_t, eqs_labels_t};
 
 use crate::errors::{check_status, check_ptr};
-use crate::{Error, TensorBlock, TensorBlockRef, Labels, LabelValue};
+use crate::{Error, TensorBlock, TensorBlockRef, Labels, LabelsBuilder, LabelValue};
 
 /// [`TensorMap`] is the main user-facing struct of this library, and can
 /// store any kind of data used in atomistic machine learning.
@@ -377,6 +377,172 @@ impl TensorMap {
         return Ok(unsafe { TensorMap::from_raw(ptr) });
     }
 
+    /// Count the number of elements in the gradients with respect to
+    /// `parameter` of all the blocks in this `TensorMap` with an absolute
+    /// value larger than `threshold`.
+    ///
+    /// This returns an error if any of the blocks does not contain gradients
+    /// with respect to `parameter`, or if the gradient data is not stored in
+    /// a `ndarray::ArrayD`.
+    #[inline]
+    pub fn gradient_nonzero_count(&self, parameter: &str, threshold: f64) -> Result<usize, Error> {
+        let mut count = 0;
+        for block in self.blocks() {
+            let gradient = block.gradient(parameter).ok_or_else(|| Error {
+                code: None,
+                message: format!("missing gradient with respect to '{}' in this TensorMap", parameter),
+            })?;
+
+            count += gradient.data.try_to_array()?.iter().filter(|v| v.abs() > threshold).count();
+        }
+
+        return Ok(count);
+    }
+
+    /// Create a copy of this `TensorMap` where all blocks contain gradients
+    /// with respect to each of the given `parameters`.
+    ///
+    /// Blocks missing one of these gradients get a zero-filled gradient, with
+    /// one gradient sample (named `sample`) for each of the values samples,
+    /// and the same components as the values. Parameters given multiple times
+    /// are only added once.
+    ///
+    /// Since all blocks share the same set of gradients, existing gradients
+    /// are copied as-is and new gradients only use the values components.
+    #[inline]
+    pub fn with_universal_gradients(&self, parameters: &[&str]) -> Result<TensorMap, Error> {
+        let mut blocks = Vec::new();
+        for block in self.blocks() {
+            let mut new_block = block.try_clone()?;
+
+            let values = block.values();
+            let mut existing = block.gradient_list();
+            for &parameter in parameters {
+                if existing.contains(&parameter) {
+                    continue;
+                }
+                existing.push(parameter);
+
+                let mut samples = LabelsBuilder::new(vec!["sample"]);
+                samples.reserve(values.samples.count());
+                for sample_i in 0..values.samples.count() {
+                    samples.add(&[sample_i]);
+                }
+
+                let shape = values.data.as_raw().shape()?.to_vec();
+                new_block.add_gradient(
+                    parameter,
+                    ndarray::ArrayD::<f64>::zeros(shape),
+                    samples.finish(),
+                    &values.components,
+                )?;
+            }
+
+            blocks.push(new_block);
+        }
+
+        return TensorMap::new(self.keys().clone(), blocks);
+    }
+
+    /// Compute a hash of all the metadata in this `TensorMap`.
+    ///
+    /// This includes the keys, the samples, components and properties of the
+    /// values in all blocks, and the parameters and labels of all gradients.
+    /// Two `TensorMap` with the same metadata will always have the same hash,
+    /// so this can be used as a fast check before comparing the data.
+    ///
+    /// The hash is computed with the standard library `DefaultHasher`, which
+    /// does not specify its algorithm and can change between Rust releases.
+    /// Hashes are only comparable within a single build of a program: they
+    /// must not be persisted, or compared with hashes computed by another
+    /// binary.
+    #[inline]
+    pub fn metadata_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.keys().hash(&mut hasher);
+        for block in self.blocks() {
+            let values = block.values();
+            values.samples.hash(&mut hasher);
+            values.components.hash(&mut hasher);
+            values.properties.hash(&mut hasher);
+
+            // gradients are not stored in a specific order, sort them to
+            // get a deterministic hash
+            let mut parameters = block.gradient_list();
+            parameters.sort_unstable();
+            for parameter in parameters {
+                let gradient = block.gradient(parameter).expect("missing gradient");
+                parameter.hash(&mut hasher);
+                gradient.samples.hash(&mut hasher);
+                gradient.components.hash(&mut hasher);
+            }
+        }
+
+        return hasher.finish();
+    }
+
+    /// Check that the data in this `TensorMap` follows the conventions used by
+    /// machine learning potentials.
+    ///
+    /// Currently, this checks that if a `"forces"` gradient exists, it has an
+    /// extra `xyz` component of size 3, and samples labels named `sample,
+    /// system, atom`. All the violated invariants are returned as a list of
+    /// human-readable messages.
+    #[inline]
+    pub fn check_eqv_invariants(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for (key, block) in self {
+            let gradient = match block.gradient("forces") {
+                Some(gradient) => gradient,
+                None => continue,
+            };
+
+            let key = self.keys().names()
+                .iter().zip(key)
+                .map(|(name, value)| format!("{} = {}", name, value))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let samples_names = gradient.samples.names();
+            if samples_names != ["sample", "system", "atom"] {
+                errors.push(format!(
+                    "forces gradient samples in block ({}) should be named \
+                    [sample, system, atom], got [{}]",
+                    key, samples_names.join(", ")
+                ));
+            }
+
+            let n_extra_components = gradient.components.len() - block.values().components.len();
+            let xyz_component = gradient.components[..n_extra_components].iter().find(|component| {
+                component.names() == ["xyz"]
+            });
+
+            match xyz_component {
+                Some(component) => {
+                    if component.count() != 3 {
+                        errors.push(format!(
+                            "the xyz component of forces gradient in block ({}) \
+                            should have 3 entries, got {}",
+                            key, component.count()
+                        ));
+                    }
+                }
+                None => errors.push(format!(
+                    "forces gradient in block ({}) is missing an extra xyz component",
+                    key
+                )),
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(());
+        } else {
+            return Err(errors);
+        }
+    }
+
     /// Get an iterator over the keys and associated blocks
     #[inline]
     pub fn iter(&self) -> TensorMapIter<'_> {
@@ -635,4 +801,239 @@ mod tests {
             assert_eq!(array[[0, 0]], 2.0 * (key[0].i32() as f64));
         }
     }
+
+    #[test]
+    fn gradient_nonzero_count() {
+        let mut block_1 = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 3], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[-2], [0], [1]]),
+        ).unwrap();
+        block_1.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_shape_vec(vec![2, 3], vec![0.0, 1e-6, -2.0, 3.0, 0.0, -1e-12]).unwrap(),
+            Labels::new(["sample", "parameter"], &[[0, 0], [1, 2]]),
+            &[],
+        ).unwrap();
+
+        let mut block_2 = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![1, 1], 3.0),
+            Labels::new(["samples"], &[[1]]),
+            &[],
+            Labels::new(["properties"], &[[1]]),
+        ).unwrap();
+        block_2.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_elem(vec![1, 1], 4.0),
+            Labels::new(["sample", "parameter"], &[[0, 1]]),
+            &[],
+        ).unwrap();
+
+        let tensor = TensorMap::new(
+            Labels::new(["key"], &[[1], [3]]),
+            vec![block_1, block_2],
+        ).unwrap();
+
+        assert_eq!(tensor.gradient_nonzero_count("parameter", 0.0).unwrap(), 5);
+        assert_eq!(tensor.gradient_nonzero_count("parameter", 1e-3).unwrap(), 3);
+
+        let error = tensor.gradient_nonzero_count("not-there", 0.0).unwrap_err();
+        assert_eq!(error.message, "missing gradient with respect to 'not-there' in this TensorMap");
+    }
+
+    #[test]
+    fn with_universal_gradients() {
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 3, 4], 1.0),
+            Labels::new(["samples"], &[[0], [4]]),
+            &[Labels::new(["components"], &[[0], [1], [2]])],
+            Labels::new(["properties"], &[[-2], [0], [1], [5]]),
+        ).unwrap();
+        block.add_gradient(
+            "existing",
+            ndarray::ArrayD::from_elem(vec![1, 3, 4], 2.0),
+            Labels::new(["sample", "parameter"], &[[1, 3]]),
+            &[Labels::new(["components"], &[[0], [1], [2]])],
+        ).unwrap();
+
+        let tensor = TensorMap::new(Labels::new(["key"], &[[0]]), vec![block]).unwrap();
+        let tensor = tensor.with_universal_gradients(&["existing", "new", "new"]).unwrap();
+
+        let block = tensor.block_by_id(0);
+        let mut parameters = block.gradient_list();
+        parameters.sort_unstable();
+        assert_eq!(parameters, ["existing", "new"]);
+
+        let existing = block.gradient("existing").unwrap();
+        assert_eq!(existing.data.as_array(), ndarray::ArrayD::from_elem(vec![1, 3, 4], 2.0));
+
+        let new = block.gradient("new").unwrap();
+        assert_eq!(new.samples, Labels::new(["sample"], &[[0], [1]]));
+        assert_eq!(new.components, [Labels::new(["components"], &[[0], [1], [2]])]);
+        assert_eq!(new.data.as_array(), ndarray::ArrayD::from_elem(vec![2, 3, 4], 0.0));
+
+        // blocks with different samples and components
+        let block_1 = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![3, 2, 1], 1.0),
+            Labels::new(["samples"], &[[0], [1], [2]]),
+            &[Labels::new(["components"], &[[0], [1]])],
+            Labels::new(["properties"], &[[0]]),
+        ).unwrap();
+        let block_2 = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![1, 3, 1], 1.0),
+            Labels::new(["samples"], &[[5]]),
+            &[Labels::new(["components"], &[[-1], [0], [1]])],
+            Labels::new(["properties"], &[[0]]),
+        ).unwrap();
+
+        let tensor = TensorMap::new(Labels::new(["key"], &[[0], [1]]), vec![block_1, block_2]).unwrap();
+        let tensor = tensor.with_universal_gradients(&["new"]).unwrap();
+
+        let new = tensor.block_by_id(0).gradient("new").unwrap();
+        assert_eq!(new.samples, Labels::new(["sample"], &[[0], [1], [2]]));
+        assert_eq!(new.components, [Labels::new(["components"], &[[0], [1]])]);
+        assert_eq!(new.data.as_array(), ndarray::ArrayD::from_elem(vec![3, 2, 1], 0.0));
+
+        let new = tensor.block_by_id(1).gradient("new").unwrap();
+        assert_eq!(new.samples, Labels::new(["sample"], &[[0]]));
+        assert_eq!(new.components, [Labels::new(["components"], &[[-1], [0], [1]])]);
+        assert_eq!(new.data.as_array(), ndarray::ArrayD::from_elem(vec![1, 3, 1], 0.0));
+    }
+
+    #[test]
+    fn metadata_hash() {
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 2], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1]]),
+        ).unwrap();
+        block.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_elem(vec![1, 2], 1.0),
+            Labels::new(["sample", "parameter"], &[[1, 1]]),
+            &[],
+        ).unwrap();
+        let tensor = TensorMap::new(Labels::new(["key"], &[[0]]), vec![block]).unwrap();
+
+        assert_eq!(tensor.metadata_hash(), tensor.metadata_hash());
+        assert_eq!(tensor.metadata_hash(), tensor.try_clone().unwrap().metadata_hash());
+
+        // the data is not part of the hash
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 2], 3.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [1]]),
+        ).unwrap();
+        block.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_elem(vec![1, 2], 3.0),
+            Labels::new(["sample", "parameter"], &[[1, 1]]),
+            &[],
+        ).unwrap();
+        let other = TensorMap::new(Labels::new(["key"], &[[0]]), vec![block]).unwrap();
+        assert_eq!(tensor.metadata_hash(), other.metadata_hash());
+
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 2], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["properties"], &[[0], [2]]),
+        ).unwrap();
+        block.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_elem(vec![1, 2], 1.0),
+            Labels::new(["sample", "parameter"], &[[1, 1]]),
+            &[],
+        ).unwrap();
+        let other = TensorMap::new(Labels::new(["key"], &[[0]]), vec![block]).unwrap();
+        assert_ne!(tensor.metadata_hash(), other.metadata_hash());
+
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![2, 2], 1.0),
+            Labels::new(["samples"], &[[0], [1]]),
+            &[],
+            Labels::new(["other"], &[[0], [1]]),
+        ).unwrap();
+        block.add_gradient(
+            "parameter",
+            ndarray::ArrayD::from_elem(vec![1, 2], 1.0),
+            Labels::new(["sample", "parameter"], &[[1, 1]]),
+            &[],
+        ).unwrap();
+        let other = TensorMap::new(Labels::new(["key"], &[[0]]), vec![block]).unwrap();
+        assert_ne!(tensor.metadata_hash(), other.metadata_hash());
+    }
+
+    #[test]
+    fn check_eqv_invariants() {
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![1, 1], 1.0),
+            Labels::new(["system"], &[[0]]),
+            &[],
+            Labels::new(["energy"], &[[0]]),
+        ).unwrap();
+        block.add_gradient(
+            "forces",
+            ndarray::ArrayD::from_elem(vec![2, 3, 1], 1.0),
+            Labels::new(["sample", "system", "atom"], &[[0, 0, 0], [0, 0, 1]]),
+            &[Labels::new(["xyz"], &[[0], [1], [2]])],
+        ).unwrap();
+        let tensor = TensorMap::new(Labels::new(["key"], &[[0]]), vec![block]).unwrap();
+        assert!(tensor.check_eqv_invariants().is_ok());
+
+        let mut block_1 = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![1, 1], 1.0),
+            Labels::new(["system"], &[[0]]),
+            &[],
+            Labels::new(["energy"], &[[0]]),
+        ).unwrap();
+        block_1.add_gradient(
+            "forces",
+            ndarray::ArrayD::from_elem(vec![2, 2, 1], 1.0),
+            Labels::new(["sample", "atom"], &[[0, 0], [0, 1]]),
+            &[Labels::new(["xyz"], &[[0], [1]])],
+        ).unwrap();
+
+        let mut block_2 = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![1, 1], 1.0),
+            Labels::new(["system"], &[[0]]),
+            &[],
+            Labels::new(["energy"], &[[0]]),
+        ).unwrap();
+        block_2.add_gradient(
+            "forces",
+            ndarray::ArrayD::from_elem(vec![1, 3, 1], 1.0),
+            Labels::new(["sample", "atom"], &[[0, 3]]),
+            &[Labels::new(["xyz"], &[[0], [1], [2]])],
+        ).unwrap();
+
+        let tensor = TensorMap::new(Labels::new(["key"], &[[0], [1]]), vec![block_1, block_2]).unwrap();
+
+        let errors = tensor.check_eqv_invariants().unwrap_err();
+        assert_eq!(errors, [
+            "forces gradient samples in block (key = 0) should be named [sample, system, atom], got [sample, atom]",
+            "the xyz component of forces gradient in block (key = 0) should have 3 entries, got 2",
+            "forces gradient samples in block (key = 1) should be named [sample, system, atom], got [sample, atom]",
+        ]);
+
+        let mut block = TensorBlock::new(
+            ndarray::ArrayD::from_elem(vec![1, 1], 1.0),
+            Labels::new(["system"], &[[0]]),
+            &[],
+            Labels::new(["energy"], &[[0]]),
+        ).unwrap();
+        block.add_gradient(
+            "forces",
+            ndarray::ArrayD::from_elem(vec![1, 3, 1], 1.0),
+            Labels::new(["sample", "system", "atom"], &[[0, 0, 0]]),
+            &[Labels::new(["direction"], &[[0], [1], [2]])],
+        ).unwrap();
+        let tensor = TensorMap::new(Labels::new(["key"], &[[0]]), vec![block]).unwrap();
+
+        let errors = tensor.check_eqv_invariants().unwrap_err();
+        assert_eq!(errors, ["forces gradient in block (key = 0) is missing an extra xyz component"]);
+    }
 }